@@ -0,0 +1,65 @@
+// SPDX-FileCopyrightText: Copyright (c) 2024-2025 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+
+use super::ValidationPolicy;
+
+/// NVIDIA-specific extensions to the OpenAI request schema.
+///
+/// These fields are read from the `nvext` field of incoming OpenAI requests and let
+/// clients opt into behavior that has no equivalent in the standard OpenAI API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NvExt {
+    /// When `true`, forces greedy sampling (argmax) and disables `temperature`/`top_p`.
+    pub greed_sampling: Option<bool>,
+
+    /// When `true`, ignores the model's end-of-sequence token so generation runs
+    /// until `max_tokens` or another stop condition is reached.
+    pub ignore_eos: Option<bool>,
+
+    /// Per-request override for how out-of-range sampling/stop values are handled.
+    ///
+    /// See [`ValidationPolicy`]. When absent, the provider's configured default applies.
+    pub validation_policy: Option<ValidationPolicy>,
+
+    /// The number of highest-probability tokens to consider at each sampling step.
+    pub top_k: Option<i32>,
+
+    /// The minimum token probability, scaled by the probability of the most likely token.
+    pub min_p: Option<f32>,
+
+    /// The random seed used for sampling, for reproducible outputs.
+    pub seed: Option<i64>,
+
+    /// Penalizes tokens based on their existing frequency in the generated text so far.
+    pub repetition_penalty: Option<f32>,
+
+    /// Whether to use beam search instead of sampling.
+    pub use_beam_search: Option<bool>,
+
+    /// Exponential penalty applied to sequence length during beam search.
+    pub length_penalty: Option<f32>,
+
+    /// The number of independent completions to generate.
+    pub n: Option<i32>,
+
+    /// The number of candidate completions to generate server-side before returning `n` of them.
+    pub best_of: Option<i32>,
+
+    /// The number of most-likely alternative tokens to return per position, if the request
+    /// asked for logprobs.
+    pub top_logprobs: Option<u32>,
+}
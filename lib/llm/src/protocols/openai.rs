@@ -67,12 +67,85 @@ pub const MAX_PRESENCE_PENALTY: f32 = 2.0;
 /// Allowed range of values for OpenAI's `presence_penalty` sampling option
 pub const PRESENCE_PENALTY_RANGE: (f32, f32) = (MIN_PRESENCE_PENALTY, MAX_PRESENCE_PENALTY);
 
+/// Minimum allowed value for the `top_k` sampling option
+pub const MIN_TOP_K: i32 = 0;
+
+/// Maximum allowed value for the `top_k` sampling option
+pub const MAX_TOP_K: i32 = i32::MAX;
+
+/// Allowed range of values for the `top_k` sampling option
+pub const TOP_K_RANGE: (i32, i32) = (MIN_TOP_K, MAX_TOP_K);
+
+/// Minimum allowed value for the `min_p` sampling option
+pub const MIN_MIN_P: f32 = 0.0;
+
+/// Maximum allowed value for the `min_p` sampling option
+pub const MAX_MIN_P: f32 = 1.0;
+
+/// Allowed range of values for the `min_p` sampling option
+pub const MIN_P_RANGE: (f32, f32) = (MIN_MIN_P, MAX_MIN_P);
+
+/// Minimum allowed value for the `repetition_penalty` sampling option
+pub const MIN_REPETITION_PENALTY: f32 = f32::MIN_POSITIVE;
+
+/// Maximum allowed value for the `repetition_penalty` sampling option
+pub const MAX_REPETITION_PENALTY: f32 = f32::MAX;
+
+/// Allowed range of values for the `repetition_penalty` sampling option
+pub const REPETITION_PENALTY_RANGE: (f32, f32) = (MIN_REPETITION_PENALTY, MAX_REPETITION_PENALTY);
+
+/// Minimum allowed value for OpenAI's `top_logprobs` option
+pub const MIN_TOP_LOGPROBS: u32 = 0;
+
+/// Maximum allowed value for OpenAI's `top_logprobs` option
+pub const MAX_TOP_LOGPROBS: u32 = 20;
+
+/// Allowed range of values for OpenAI's `top_logprobs` option
+pub const TOP_LOGPROBS_RANGE: (u32, u32) = (MIN_TOP_LOGPROBS, MAX_TOP_LOGPROBS);
+
+/// Policy controlling how out-of-range sampling/stop values are handled.
+///
+/// Selectable per-request via `nvext.validation_policy` (see
+/// [`OpenAISamplingOptionsProvider::validation_policy`]); falls back to [`ValidationPolicy::Strict`]
+/// when unset, preserving today's behavior.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationPolicy {
+    /// Reject the request if any value falls outside its allowed range.
+    #[default]
+    Strict,
+    /// Silently clamp out-of-range values to their nearest bound instead of erroring.
+    Clamp,
+    /// Skip range validation entirely and pass values through untouched.
+    Disabled,
+}
+
+/// Resolves the [`ValidationPolicy`] for a request: a per-request `nvext.validation_policy`
+/// override wins; otherwise `default` applies.
+///
+/// Shared by [`OpenAISamplingOptionsProvider::validation_policy`] and
+/// [`OpenAIStopConditionsProvider::validation_policy`], since the resolution rule is identical
+/// for both. Operators that want to trade strictness for resilience across all requests should
+/// override `default_validation_policy` (e.g. by reading a field the concrete provider was
+/// constructed with) rather than relying on the conservative [`ValidationPolicy::Strict`]
+/// fallback those traits default to.
+fn resolve_validation_policy(
+    nvext: Option<&nvext::NvExt>,
+    default: ValidationPolicy,
+) -> ValidationPolicy {
+    nvext
+        .and_then(|nvext| nvext.validation_policy)
+        .unwrap_or(default)
+}
+
 /// Represents a streaming response from the OpenAI API
 /// The object is generalized on R, which is the type of the response.
 /// For SSE streaming responses, the expected `data: ` field is always a JSON
 /// object corresponding to `R`; however, the comments in the SSE stream `: `
 /// may correspond to other types of information, such as performance metrics,
-/// as represented by other arms of this enum.
+/// as represented by other arms of this enum. Structured comments (see
+/// [`CommentPayload`]) are serialized as tagged JSON so a client-side parser can
+/// tell them apart from an arbitrary comment string.
 ///
 /// This is part of the common API as both the client and service need to agree
 /// on the format of the streaming responses.
@@ -83,6 +156,21 @@ pub enum StreamingDelta<R> {
     Comment(String),
 }
 
+impl<R> StreamingDelta<R> {
+    /// Returns the structured metrics payload carried by this delta's comment, if any.
+    ///
+    /// `Delta` frames and comments that aren't a recognized [`CommentPayload`] return `None`.
+    pub fn metrics(&self) -> Option<StreamMetrics> {
+        match self {
+            StreamingDelta::Comment(raw) => match CommentPayload::from_comment_str(raw) {
+                Ok(Some(CommentPayload::Metrics(metrics))) => Some(metrics),
+                _ => None,
+            },
+            StreamingDelta::Delta(_) => None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AnnotatedDelta<R> {
     pub delta: R,
@@ -91,6 +179,56 @@ pub struct AnnotatedDelta<R> {
     pub comment: Option<String>,
 }
 
+/// Out-of-band performance metrics for an in-flight request.
+///
+/// See [`DeltaGeneratorExt::get_isl`] for `input_sequence_length`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StreamMetrics {
+    /// The number of prompt tokens (Input Sequence Length).
+    pub input_sequence_length: Option<u32>,
+
+    /// The number of tokens generated so far for this request.
+    pub output_sequence_length: Option<u32>,
+
+    /// Time from request start to the first generated token, in milliseconds.
+    pub time_to_first_token_ms: Option<f64>,
+
+    /// Average time between consecutive generated tokens, in milliseconds.
+    pub inter_token_latency_ms: Option<f64>,
+
+    /// Generated tokens per second, averaged over the request so far.
+    pub tokens_per_second: Option<f64>,
+}
+
+/// Typed payload carried on the SSE comment channel (see [`StreamingDelta::Comment`]).
+///
+/// Comments are serialized as a tagged JSON object so a client-side parser can tell a
+/// structured payload apart from an arbitrary comment string without guessing.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommentPayload {
+    /// Out-of-band performance metrics for the in-flight request.
+    Metrics(StreamMetrics),
+}
+
+impl CommentPayload {
+    /// Serializes this payload for transport as a raw SSE comment line's contents.
+    pub fn to_comment_string(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parses a raw SSE comment line's contents back into a typed payload.
+    ///
+    /// Returns `Ok(None)` when the comment isn't a recognized structured payload, so
+    /// callers can fall back to treating it as an opaque string.
+    pub fn from_comment_str(raw: &str) -> Result<Option<Self>> {
+        match serde_json::from_str(raw) {
+            Ok(payload) => Ok(Some(payload)),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
 trait OpenAISamplingOptionsProvider {
     fn get_temperature(&self) -> Option<f32>;
 
@@ -101,6 +239,81 @@ trait OpenAISamplingOptionsProvider {
     fn get_presence_penalty(&self) -> Option<f32>;
 
     fn nvext(&self) -> Option<&nvext::NvExt>;
+
+    /// The number of highest-probability tokens to consider at each step.
+    ///
+    /// Defaults to `nvext.top_k`.
+    fn get_top_k(&self) -> Option<i32> {
+        self.nvext().and_then(|nvext| nvext.top_k)
+    }
+
+    /// The minimum token probability, scaled by the probability of the most likely token.
+    ///
+    /// Defaults to `nvext.min_p`.
+    fn get_min_p(&self) -> Option<f32> {
+        self.nvext().and_then(|nvext| nvext.min_p)
+    }
+
+    /// The random seed used for sampling, for reproducible outputs.
+    ///
+    /// Defaults to `nvext.seed`.
+    fn get_seed(&self) -> Option<i64> {
+        self.nvext().and_then(|nvext| nvext.seed)
+    }
+
+    /// Penalizes tokens based on their existing frequency in the generated text so far.
+    ///
+    /// Defaults to `nvext.repetition_penalty`.
+    fn get_repetition_penalty(&self) -> Option<f32> {
+        self.nvext().and_then(|nvext| nvext.repetition_penalty)
+    }
+
+    /// Whether to use beam search instead of sampling.
+    ///
+    /// Defaults to `nvext.use_beam_search`.
+    fn get_use_beam_search(&self) -> Option<bool> {
+        self.nvext().and_then(|nvext| nvext.use_beam_search)
+    }
+
+    /// Exponential penalty applied to sequence length during beam search.
+    ///
+    /// Defaults to `nvext.length_penalty`.
+    fn get_length_penalty(&self) -> Option<f32> {
+        self.nvext().and_then(|nvext| nvext.length_penalty)
+    }
+
+    /// The number of independent completions to generate.
+    ///
+    /// Defaults to `nvext.n`.
+    fn get_n(&self) -> Option<i32> {
+        self.nvext().and_then(|nvext| nvext.n)
+    }
+
+    /// The number of candidate completions to generate server-side before returning `n` of them.
+    ///
+    /// Defaults to `nvext.best_of`.
+    fn get_best_of(&self) -> Option<i32> {
+        self.nvext().and_then(|nvext| nvext.best_of)
+    }
+
+    /// The number of most-likely alternative tokens to return per position, if the request
+    /// asked for logprobs.
+    ///
+    /// Defaults to `nvext.top_logprobs`.
+    fn get_top_logprobs(&self) -> Option<u32> {
+        self.nvext().and_then(|nvext| nvext.top_logprobs)
+    }
+
+    /// The server-configured default validation policy; see [`resolve_validation_policy`].
+    fn default_validation_policy(&self) -> ValidationPolicy {
+        ValidationPolicy::Strict
+    }
+
+    /// The validation policy to apply to this request's sampling options; see
+    /// [`resolve_validation_policy`].
+    fn validation_policy(&self) -> ValidationPolicy {
+        resolve_validation_policy(self.nvext(), self.default_validation_policy())
+    }
 }
 
 trait OpenAIStopConditionsProvider {
@@ -111,6 +324,17 @@ trait OpenAIStopConditionsProvider {
     fn get_stop(&self) -> Option<Vec<String>>;
 
     fn nvext(&self) -> Option<&nvext::NvExt>;
+
+    /// The server-configured default validation policy; see [`resolve_validation_policy`].
+    fn default_validation_policy(&self) -> ValidationPolicy {
+        ValidationPolicy::Strict
+    }
+
+    /// The validation policy to apply to this request's stop conditions; see
+    /// [`resolve_validation_policy`].
+    fn validation_policy(&self) -> ValidationPolicy {
+        resolve_validation_policy(self.nvext(), self.default_validation_policy())
+    }
 }
 
 impl<T: OpenAISamplingOptionsProvider> SamplingOptionsProvider for T {
@@ -120,15 +344,30 @@ impl<T: OpenAISamplingOptionsProvider> SamplingOptionsProvider for T {
         //     return Err(format!("Error validating sampling options: {}", e));
         // }
 
-        let mut temperature = validate_range(self.get_temperature(), &TEMPERATURE_RANGE)
+        let policy = self.validation_policy();
+
+        let mut temperature = validate_range(self.get_temperature(), &TEMPERATURE_RANGE, policy)
             .map_err(|e| anyhow::anyhow!("Error validating temperature: {}", e))?;
-        let mut top_p = validate_range(self.get_top_p(), &TOP_P_RANGE)
+        let mut top_p = validate_range(self.get_top_p(), &TOP_P_RANGE, policy)
             .map_err(|e| anyhow::anyhow!("Error validating top_p: {}", e))?;
         let frequency_penalty =
-            validate_range(self.get_frequency_penalty(), &FREQUENCY_PENALTY_RANGE)
+            validate_range(self.get_frequency_penalty(), &FREQUENCY_PENALTY_RANGE, policy)
                 .map_err(|e| anyhow::anyhow!("Error validating frequency_penalty: {}", e))?;
-        let presence_penalty = validate_range(self.get_presence_penalty(), &PRESENCE_PENALTY_RANGE)
-            .map_err(|e| anyhow::anyhow!("Error validating presence_penalty: {}", e))?;
+        let presence_penalty =
+            validate_range(self.get_presence_penalty(), &PRESENCE_PENALTY_RANGE, policy)
+                .map_err(|e| anyhow::anyhow!("Error validating presence_penalty: {}", e))?;
+        let top_k = validate_range(self.get_top_k(), &TOP_K_RANGE, policy)
+            .map_err(|e| anyhow::anyhow!("Error validating top_k: {}", e))?;
+        let min_p = validate_range(self.get_min_p(), &MIN_P_RANGE, policy)
+            .map_err(|e| anyhow::anyhow!("Error validating min_p: {}", e))?;
+        let repetition_penalty =
+            validate_range(self.get_repetition_penalty(), &REPETITION_PENALTY_RANGE, policy)
+                .map_err(|e| anyhow::anyhow!("Error validating repetition_penalty: {}", e))?;
+        let seed = self.get_seed();
+        let use_beam_search = self.get_use_beam_search();
+        let length_penalty = self.get_length_penalty();
+        let n = self.get_n();
+        let best_of = self.get_best_of();
 
         if let Some(nvext) = self.nvext() {
             let greedy = nvext.greed_sampling.unwrap_or(false);
@@ -139,31 +378,37 @@ impl<T: OpenAISamplingOptionsProvider> SamplingOptionsProvider for T {
         }
 
         Ok(common::SamplingOptions {
-            n: None,
-            best_of: None,
+            n,
+            best_of,
             frequency_penalty,
             presence_penalty,
-            repetition_penalty: None,
+            repetition_penalty,
             temperature,
             top_p,
-            top_k: None,
-            min_p: None,
-            seed: None,
-            use_beam_search: None,
-            length_penalty: None,
+            top_k,
+            min_p,
+            seed,
+            use_beam_search,
+            length_penalty,
         })
     }
 }
 
 impl<T: OpenAIStopConditionsProvider> StopConditionsProvider for T {
     fn extract_stop_conditions(&self) -> Result<common::StopConditions> {
+        let policy = self.validation_policy();
+
         let max_tokens = self.get_max_tokens();
         let min_tokens = self.get_min_tokens();
-        let stop = self.get_stop();
+        let mut stop = self.get_stop();
 
-        if let Some(stop) = &stop {
+        if let Some(stop) = &mut stop {
             if stop.len() > 4 {
-                anyhow::bail!("stop conditions must be less than 4")
+                match policy {
+                    ValidationPolicy::Strict => anyhow::bail!("stop conditions must be at most 4"),
+                    ValidationPolicy::Clamp => stop.truncate(4),
+                    ValidationPolicy::Disabled => {}
+                }
             }
         }
 
@@ -217,22 +462,81 @@ pub struct GenericCompletionResponse<C>
     /// This field is not supported by the NIM; however it will be added in the future.
     /// The optional nature of this field will be relaxed when it is supported.
     pub system_fingerprint: Option<String>,
+
+    /// Per-token log probabilities for each entry in `choices`, aligned by index.
+    ///
+    /// `None` at an index means that choice carries no logprobs. An entry is built with
+    /// [`DeltaGeneratorExt::build_logprobs`]; [`DeltaGeneratorExt::choice_from_postprocessor`]
+    /// does not call it and does not set this field.
+    pub logprobs: Option<Vec<Option<LogProbs>>>,
     // TODO() - add NvResponseExtention
 }
 
+/// One of the `top_logprobs` alternative tokens considered at a given position.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TopLogProb {
+    /// The text of the candidate token.
+    pub token: String,
+
+    /// The log probability of this candidate token.
+    pub logprob: f32,
+
+    /// The UTF-8 byte representation of the token, when available.
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// Per-token log-probability information for a generated choice.
+///
+/// Built by [`DeltaGeneratorExt::build_logprobs`] when the request sets `top_logprobs`;
+/// [`DeltaGeneratorExt::choice_from_postprocessor`] does not build it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogProbs {
+    /// The sampled token at each generated position.
+    pub tokens: Vec<String>,
+
+    /// The log probability of the sampled token at each position, if known.
+    pub token_logprobs: Vec<Option<f32>>,
+
+    /// The byte offset of each token within the generated text.
+    pub text_offset: Vec<usize>,
+
+    /// The `top_logprobs` most likely alternative tokens considered at each position.
+    pub top_logprobs: Vec<Vec<TopLogProb>>,
+}
+
 // todo - move to common location
-fn validate_range<T>(value: Option<T>, range: &(T, T)) -> Result<Option<T>>
+/// Validates a requested `top_logprobs` count against [`TOP_LOGPROBS_RANGE`].
+fn validate_top_logprobs(value: Option<u32>, policy: ValidationPolicy) -> Result<Option<u32>> {
+    validate_range(value, &TOP_LOGPROBS_RANGE, policy)
+        .map_err(|e| anyhow::anyhow!("Error validating top_logprobs: {}", e))
+}
+
+// todo - move to common location
+fn validate_range<T>(value: Option<T>, range: &(T, T), policy: ValidationPolicy) -> Result<Option<T>>
 where
-    T: PartialOrd + Display,
+    T: PartialOrd + Display + Copy,
 {
-    if value.is_none() {
+    let Some(value) = value else {
         return Ok(None);
+    };
+    match policy {
+        ValidationPolicy::Disabled => Ok(Some(value)),
+        ValidationPolicy::Clamp => {
+            if value < range.0 {
+                Ok(Some(range.0))
+            } else if value > range.1 {
+                Ok(Some(range.1))
+            } else {
+                Ok(Some(value))
+            }
+        }
+        ValidationPolicy::Strict => {
+            if value < range.0 || value > range.1 {
+                anyhow::bail!("Value {} is out of range [{}, {}]", value, range.0, range.1);
+            }
+            Ok(Some(value))
+        }
     }
-    let value = value.unwrap();
-    if value < range.0 || value > range.1 {
-        anyhow::bail!("Value {} is out of range [{}, {}]", value, range.0, range.1);
-    }
-    Ok(Some(value))
 }
 
 // todo - move to common location
@@ -269,6 +573,72 @@ pub trait DeltaGeneratorExt<ResponseType: Send + Sync + 'static + std::fmt::Debu
 
     /// Gets the current prompt token count (Input Sequence Length).
     fn get_isl(&self) -> Option<u32>;
+
+    /// Builds an SSE comment frame carrying structured performance metrics for this request.
+    ///
+    /// Pairs [`Self::get_isl`] with the output-side counters/timings the caller is tracking
+    /// while driving [`Self::choice_from_postprocessor`] into a [`StreamMetrics`] payload,
+    /// serialized as a [`StreamingDelta::Comment`] via [`CommentPayload::Metrics`]. Callers
+    /// would stream the result alongside `Delta` frames so clients can observe live performance
+    /// without it polluting the `data:` stream.
+    ///
+    /// Scaffolding: no `choice_from_postprocessor` implementation in this tree calls this yet —
+    /// wiring a concrete generator's streaming loop to emit these comments is follow-up work.
+    fn build_metrics_comment(
+        &self,
+        output_sequence_length: Option<u32>,
+        time_to_first_token_ms: Option<f64>,
+        inter_token_latency_ms: Option<f64>,
+        tokens_per_second: Option<f64>,
+    ) -> Result<StreamingDelta<ResponseType>> {
+        let metrics = StreamMetrics {
+            input_sequence_length: self.get_isl(),
+            output_sequence_length,
+            time_to_first_token_ms,
+            inter_token_latency_ms,
+            tokens_per_second,
+        };
+        let raw = CommentPayload::Metrics(metrics).to_comment_string()?;
+        Ok(StreamingDelta::Comment(raw))
+    }
+
+    /// The number of top alternative log-probabilities requested for this request, if any.
+    ///
+    /// Implementations backed by an OpenAI request should return
+    /// [`OpenAISamplingOptionsProvider::get_top_logprobs`]; defaults to `None` (no logprobs).
+    fn get_top_logprobs(&self) -> Option<u32> {
+        None
+    }
+
+    /// Builds the `logprobs` entry for a single choice from postprocessor output.
+    ///
+    /// Validates [`Self::get_top_logprobs`] against [`TOP_LOGPROBS_RANGE`] under `policy` and,
+    /// when requested, pairs it with the tokens/log-probabilities the backend produced, along
+    /// with `top_candidates` — the backend's per-position alternative-token list, one entry per
+    /// position in `tokens` — truncated to the validated `top_logprobs` count. Implementations
+    /// of [`Self::choice_from_postprocessor`] should call this to populate
+    /// [`GenericCompletionResponse::logprobs`] for the choice being built.
+    fn build_logprobs(
+        &self,
+        policy: ValidationPolicy,
+        tokens: Vec<String>,
+        token_logprobs: Vec<Option<f32>>,
+        text_offset: Vec<usize>,
+        mut top_candidates: Vec<Vec<TopLogProb>>,
+    ) -> Result<Option<LogProbs>> {
+        let Some(top_logprobs) = validate_top_logprobs(self.get_top_logprobs(), policy)? else {
+            return Ok(None);
+        };
+        for candidates in &mut top_candidates {
+            candidates.truncate(top_logprobs as usize);
+        }
+        Ok(Some(LogProbs {
+            tokens,
+            token_logprobs,
+            text_offset,
+            top_logprobs: top_candidates,
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -276,26 +646,192 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_resolve_validation_policy() {
+        assert_eq!(
+            resolve_validation_policy(None, ValidationPolicy::Strict),
+            ValidationPolicy::Strict
+        );
+        assert_eq!(
+            resolve_validation_policy(None, ValidationPolicy::Clamp),
+            ValidationPolicy::Clamp
+        );
+        let nvext = nvext::NvExt {
+            validation_policy: Some(ValidationPolicy::Disabled),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_validation_policy(Some(&nvext), ValidationPolicy::Strict),
+            ValidationPolicy::Disabled
+        );
+    }
+
     #[test]
     fn test_validate_range() {
-        assert_eq!(validate_range(Some(0.5), &(0.0, 1.0)).unwrap(), Some(0.5));
-        assert_eq!(validate_range(Some(0.0), &(0.0, 1.0)).unwrap(), Some(0.0));
-        assert_eq!(validate_range(Some(1.0), &(1.0, 1.0)).unwrap(), Some(1.0));
-        assert_eq!(validate_range(Some(1_i32), &(1, 1)).unwrap(), Some(1));
+        let strict = ValidationPolicy::Strict;
+        assert_eq!(
+            validate_range(Some(0.5), &(0.0, 1.0), strict).unwrap(),
+            Some(0.5)
+        );
+        assert_eq!(
+            validate_range(Some(0.0), &(0.0, 1.0), strict).unwrap(),
+            Some(0.0)
+        );
         assert_eq!(
-            validate_range(Some(1.1), &(0.0, 1.0))
+            validate_range(Some(1.0), &(1.0, 1.0), strict).unwrap(),
+            Some(1.0)
+        );
+        assert_eq!(
+            validate_range(Some(1_i32), &(1, 1), strict).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            validate_range(Some(1.1), &(0.0, 1.0), strict)
                 .unwrap_err()
                 .to_string(),
             "Value 1.1 is out of range [0, 1]"
         );
         assert_eq!(
-            validate_range(Some(-0.1), &(0.0, 1.0))
+            validate_range(Some(-0.1), &(0.0, 1.0), strict)
                 .unwrap_err()
                 .to_string(),
             "Value -0.1 is out of range [0, 1]"
         );
     }
 
+    #[test]
+    fn test_validate_range_clamp() {
+        let clamp = ValidationPolicy::Clamp;
+        assert_eq!(
+            validate_range(Some(1.5), &(0.0, 1.0), clamp).unwrap(),
+            Some(1.0)
+        );
+        assert_eq!(
+            validate_range(Some(-0.5), &(0.0, 1.0), clamp).unwrap(),
+            Some(0.0)
+        );
+        assert_eq!(
+            validate_range(Some(0.5), &(0.0, 1.0), clamp).unwrap(),
+            Some(0.5)
+        );
+    }
+
+    #[test]
+    fn test_validate_range_disabled() {
+        let disabled = ValidationPolicy::Disabled;
+        assert_eq!(
+            validate_range(Some(5.0), &(0.0, 1.0), disabled).unwrap(),
+            Some(5.0)
+        );
+    }
+
+    #[test]
+    fn test_validate_top_logprobs() {
+        let strict = ValidationPolicy::Strict;
+        assert_eq!(validate_top_logprobs(None, strict).unwrap(), None);
+        assert_eq!(validate_top_logprobs(Some(5), strict).unwrap(), Some(5));
+        assert!(validate_top_logprobs(Some(21), strict).is_err());
+        assert_eq!(
+            validate_top_logprobs(Some(21), ValidationPolicy::Clamp).unwrap(),
+            Some(20)
+        );
+    }
+
+    struct FakeDeltaGenerator {
+        isl: Option<u32>,
+        top_logprobs: Option<u32>,
+    }
+
+    impl DeltaGeneratorExt<String> for FakeDeltaGenerator {
+        fn choice_from_postprocessor(
+            &mut self,
+            _response: common::llm_backend::BackendOutput,
+        ) -> Result<String> {
+            unimplemented!("not exercised by test_build_logprobs/test_build_metrics_comment")
+        }
+
+        fn get_isl(&self) -> Option<u32> {
+            self.isl
+        }
+
+        fn get_top_logprobs(&self) -> Option<u32> {
+            self.top_logprobs
+        }
+    }
+
+    #[test]
+    fn test_build_metrics_comment_carries_isl() {
+        let generator = FakeDeltaGenerator {
+            isl: Some(128),
+            top_logprobs: None,
+        };
+        let delta = generator
+            .build_metrics_comment(Some(16), Some(42.0), Some(5.5), Some(180.0))
+            .unwrap();
+        let metrics = delta.metrics().unwrap();
+        assert_eq!(metrics.input_sequence_length, Some(128));
+        assert_eq!(metrics.output_sequence_length, Some(16));
+        assert_eq!(metrics.tokens_per_second, Some(180.0));
+    }
+
+    #[test]
+    fn test_build_logprobs_none_when_not_requested() {
+        let generator = FakeDeltaGenerator {
+            isl: None,
+            top_logprobs: None,
+        };
+        let result = generator
+            .build_logprobs(ValidationPolicy::Strict, vec![], vec![], vec![], vec![])
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_build_logprobs_populates_tokens_and_truncates_candidates() {
+        let generator = FakeDeltaGenerator {
+            isl: None,
+            top_logprobs: Some(1),
+        };
+        let candidates = vec![vec![
+            TopLogProb {
+                token: "hi".to_string(),
+                logprob: -0.1,
+                bytes: None,
+            },
+            TopLogProb {
+                token: "hello".to_string(),
+                logprob: -2.0,
+                bytes: None,
+            },
+        ]];
+        let logprobs = generator
+            .build_logprobs(
+                ValidationPolicy::Strict,
+                vec!["hi".to_string()],
+                vec![Some(-0.1)],
+                vec![0],
+                candidates,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(logprobs.tokens, vec!["hi".to_string()]);
+        assert_eq!(logprobs.token_logprobs, vec![Some(-0.1)]);
+        assert_eq!(logprobs.top_logprobs.len(), 1);
+        assert_eq!(logprobs.top_logprobs[0].len(), 1);
+        assert_eq!(logprobs.top_logprobs[0][0].token, "hi");
+    }
+
+    #[test]
+    fn test_build_logprobs_rejects_out_of_range_strict() {
+        let generator = FakeDeltaGenerator {
+            isl: None,
+            top_logprobs: Some(21),
+        };
+        assert!(generator
+            .build_logprobs(ValidationPolicy::Strict, vec![], vec![], vec![], vec![])
+            .is_err());
+    }
+
     #[test]
     fn test_scaled_value() {
         assert_eq!(scale_value(&0.5, &(0.0, 1.0), &(0.0, 2.0)).unwrap(), 1.0);
@@ -303,4 +839,31 @@ mod tests {
         assert_eq!(scale_value(&-1.0, &(-2.0, 2.0), &(1.0, 2.0)).unwrap(), 1.25);
         assert!(scale_value(&1.0, &(1.0, 1.0), &(0.0, 2.0)).is_err());
     }
+
+    #[test]
+    fn test_comment_payload_round_trip() {
+        let metrics = StreamMetrics {
+            input_sequence_length: Some(128),
+            output_sequence_length: Some(16),
+            time_to_first_token_ms: Some(42.0),
+            inter_token_latency_ms: Some(5.5),
+            tokens_per_second: Some(180.0),
+        };
+        let payload = CommentPayload::Metrics(metrics.clone());
+        let raw = payload.to_comment_string().unwrap();
+
+        let delta: StreamingDelta<String> = StreamingDelta::Comment(raw);
+        let parsed = delta.metrics().unwrap();
+        assert_eq!(parsed.input_sequence_length, metrics.input_sequence_length);
+        assert_eq!(parsed.tokens_per_second, metrics.tokens_per_second);
+    }
+
+    #[test]
+    fn test_comment_payload_opaque_string() {
+        let delta: StreamingDelta<String> = StreamingDelta::Comment("keep-alive".to_string());
+        assert!(delta.metrics().is_none());
+
+        let delta: StreamingDelta<String> = StreamingDelta::Delta("unused".to_string());
+        assert!(delta.metrics().is_none());
+    }
 }